@@ -21,6 +21,34 @@ impl AppSource {
         }
     }
 
+    /// Infer a source when none was given, by looking for a `spin.toml` in the
+    /// current directory and walking up to the nearest ancestor that has one.
+    pub fn infer_from_current_dir() -> Self {
+        const MANIFEST_FILE_NAME: &str = "spin.toml";
+
+        let cwd = match std::env::current_dir() {
+            Ok(dir) => dir,
+            Err(e) => return Self::Unresolvable(format!("cannot read current directory: {e}")),
+        };
+
+        let mut dir = cwd.as_path();
+        loop {
+            let candidate = dir.join(MANIFEST_FILE_NAME);
+            if candidate.is_file() {
+                return Self::File(candidate);
+            }
+            match dir.parent() {
+                Some(parent) => dir = parent,
+                None => break,
+            }
+        }
+
+        Self::Unresolvable(format!(
+            "no {MANIFEST_FILE_NAME} found in {} or any parent directory; pass an application with `--from`",
+            cwd.display()
+        ))
+    }
+
     pub fn infer_file_source(path: impl Into<PathBuf>) -> Self {
         match spin_common::paths::resolve_manifest_file_path(path.into()) {
             Ok(file) => Self::File(file),
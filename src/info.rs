@@ -1,11 +1,15 @@
-use std::{os::unix::fs::MetadataExt, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
 
 use crate::app_source::AppSource;
 use anyhow::{bail, Context, Ok, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use human_bytes::human_bytes;
 use spin_locked_app::{
-    locked::{LockedComponent, LockedMap, LockedTrigger},
+    locked::{LockedApp, LockedComponent, LockedMap},
     values::ValuesMap,
     Variable,
 };
@@ -13,8 +17,20 @@ use spin_oci::OciLoader;
 use tempfile::TempDir;
 use walkdir::WalkDir;
 use comfy_table::Table;
-use serde_json::Value;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
 
+/// The format used to render application info.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[clap(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Human-readable tables.
+    #[default]
+    Table,
+    /// Machine-readable pretty-printed JSON.
+    Json,
+}
 
 /// Get information about a Spin applicaton's metadata.
 #[derive(Parser, Clone, Debug)]
@@ -26,20 +42,82 @@ pub struct InfoCommand {
     /// Cache directory for downloaded components and assets.
     #[clap(long)]
     pub cache_dir: Option<PathBuf>,
+
+    /// How to render the application info.
+    #[clap(long = "output", value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+
+    /// Inspect the registry image manifest and config only, listing each
+    /// layer's media type, digest and declared size without pulling any blobs.
+    #[clap(long = "layers")]
+    pub layers: bool,
+
+    /// Only show the given component(s). May be repeated; defaults to all.
+    #[clap(long = "component", value_name = "ID")]
+    pub component: Vec<String>,
 }
 
+// OCI media types used by Spin application images.
+const WASM_LAYER_MEDIA_TYPE: &str = "application/vnd.wasm.content.layer.v1+wasm";
+const DATA_LAYER_MEDIA_TYPE: &str = "application/vnd.wasm.content.layer.v1+data";
+const SPIN_CONFIG_MEDIA_TYPE: &str = "application/vnd.fermyon.spin.application.v1+config";
+
 impl InfoCommand {
     pub async fn run(self) -> Result<()> {
+        if self.layers && !self.component.is_empty() {
+            bail!("`--component` cannot be combined with `--layers`.");
+        }
+
         let app = self.app_source();
         match app {
+            AppSource::OciRegistry(app) if self.layers => self.print_manifest_registry(app).await,
             AppSource::OciRegistry(app) => self.print_info_registry(app).await,
-            AppSource::File(app) => self.print_info_local(app).await,
-            _ => bail!("Spin Info plugin only supports file or registry applications."),
+            AppSource::File(_) if self.layers => {
+                bail!("`--layers` only applies to registry applications.")
+            }
+            source @ AppSource::File(_) => self.print_info_local(source).await,
+            AppSource::Unresolvable(msg) => bail!("{msg}"),
+            AppSource::None => {
+                bail!("Spin Info plugin only supports file or registry applications.")
+            }
         }
     }
 
+    /// Inspect only the OCI image manifest and config of a registry reference.
+    ///
+    /// This fetches only the manifest and the small config blob: it never
+    /// downloads the Wasm or asset layers, so it's a cheap way to get a size
+    /// overview of an app.
+    pub async fn print_manifest_registry(&self, app: String) -> Result<()> {
+        if self.output == OutputFormat::Table {
+            println!("Getting manifest for app {:?}", app);
+        }
+
+        let mut client = spin_oci::Client::new(false, self.cache_dir.clone())
+            .await
+            .context("cannot create registry client")?;
+
+        let reference: spin_oci::oci_distribution::Reference = app
+            .parse()
+            .with_context(|| format!("could not parse {app:?} as an OCI reference"))?;
+
+        let (manifest, _digest, _config) = client
+            .oci
+            .pull_manifest_and_config(
+                &reference,
+                &spin_oci::oci_distribution::secrets::RegistryAuth::Anonymous,
+            )
+            .await
+            .context("cannot fetch image manifest")?;
+
+        let info = ManifestInfo::new(&manifest);
+        self.render(&info)
+    }
+
     pub async fn print_info_registry(&self, app: String) -> Result<()> {
-        println!("Getting info for app {:?}", app);
+        if self.output == OutputFormat::Table {
+            println!("Getting info for app {:?}", app);
+        }
 
         let mut client = spin_oci::Client::new(false, self.cache_dir.clone())
             .await
@@ -51,168 +129,399 @@ impl InfoCommand {
             .load_app(&mut client, &app)
             .await?;
 
-        self.print_metadata(&locked_app.metadata)?;
+        // Registry layers are unpacked into the temp dir as absolute paths.
+        let info = AppInfo::new(&locked_app, None, &self.component)?;
+        self.render(&info)
+    }
 
-        println!("Application will be triggered by:");
-        for t in &locked_app.triggers {
-            self.print_trigger(t);
+    pub async fn print_info_local(&self, source: AppSource) -> Result<()> {
+        let app = match &source {
+            AppSource::File(path) => path,
+            _ => unreachable!("print_info_local requires a file source"),
+        };
+
+        if self.output == OutputFormat::Table {
+            println!("Getting info for app {:?}", app);
         }
-        self.print_variables(&locked_app.variables);
-        self.print_host_requirements(&locked_app.host_requirements);
-        for c in &locked_app.components {
-            self.print_component(c)?;
+
+        // Mount files directly from the app directory rather than copying them
+        // into a temp dir, so the resulting `file://` sources point at the
+        // on-disk app and we can stat them in place.
+        let locked_app = spin_loader::from_file(
+            app,
+            spin_loader::FilesMountStrategy::Direct,
+            self.cache_dir.clone(),
+        )
+        .await
+        .with_context(|| format!("failed to load manifest {app:?}"))?;
+
+        // Local component and file sources are relative to the manifest's
+        // parent directory.
+        let info = AppInfo::new(&locked_app, source.local_app_dir(), &self.component)?;
+        self.render(&info)
+    }
+
+    /// Render the collected info either as human-readable tables or as JSON.
+    fn render<T: Render>(&self, info: &T) -> Result<()> {
+        match self.output {
+            OutputFormat::Table => info.print_tables(),
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(info)?);
+                Ok(())
+            }
         }
+    }
 
-        Ok(())
+    fn app_source(&self) -> AppSource {
+        match &self.app_source {
+            Some(src) => AppSource::infer_source(src),
+            None => AppSource::infer_from_current_dir(),
+        }
     }
+}
 
-    fn print_metadata(&self, meta: &ValuesMap) -> Result<()> {
-        // TODO: because we're getting values from the values map,
-        // the strings are quoted. Deserializing them to strings will
-        // get rid of the extra quotes.
-        let mut table = Table::new();
-        table.set_header(vec!["Key", "Value"]);
+/// Info that can be rendered either as human-readable tables or as JSON.
+trait Render: Serialize {
+    fn print_tables(&self) -> Result<()>;
+}
+
+/// A serializable snapshot of everything `spin info` reports about an app.
+///
+/// Metadata values keep their native JSON types (rather than the quoted
+/// strings the raw [`ValuesMap`] would produce), so JSON output is clean and
+/// the table renderer can unquote strings in one place.
+#[derive(Serialize)]
+struct AppInfo {
+    metadata: Map<String, Value>,
+    triggers: Vec<TriggerInfo>,
+    variables: LockedMap<Variable>,
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    host_requirements: Map<String, Value>,
+    components: Vec<ComponentInfo>,
+}
+
+#[derive(Serialize)]
+struct TriggerInfo {
+    id: String,
+    trigger_type: String,
+    trigger_config: Value,
+}
+
+#[derive(Serialize)]
+struct ComponentInfo {
+    id: String,
+    metadata: Map<String, Value>,
+    source: SourceInfo,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    env: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    files: Vec<FileInfo>,
+}
 
-        for(key, value) in meta.iter() {
-            table.add_row(vec![&key, &value.to_string()]);
+#[derive(Serialize)]
+struct SourceInfo {
+    content_type: String,
+    /// Size of the Wasm module in bytes, or `None` if it isn't built yet.
+    size: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct FileInfo {
+    path: String,
+    file_count: usize,
+    size: u64,
+}
+
+impl AppInfo {
+    fn new(app: &LockedApp, base_dir: Option<&Path>, selected: &[String]) -> Result<Self> {
+        let triggers = app
+            .triggers
+            .iter()
+            .map(|t| TriggerInfo {
+                id: t.id.clone(),
+                trigger_type: t.trigger_type.clone(),
+                trigger_config: t.trigger_config.clone(),
+            })
+            .collect();
+
+        // When components are requested by id, every requested id must exist.
+        if let Some(missing) = selected.iter().find(|id| {
+            !app.components.iter().any(|c| &c.id == *id)
+        }) {
+            let available = app
+                .components
+                .iter()
+                .map(|c| c.id.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            bail!("no component with id {missing:?}; available components: {available}");
         }
 
-        println!("Appliction Info");
-        println!("{}", table);
+        let components = app
+            .components
+            .iter()
+            .filter(|c| selected.is_empty() || selected.iter().any(|id| id == &c.id))
+            .map(|c| ComponentInfo::new(c, base_dir))
+            .collect::<Result<Vec<_>>>()?;
 
-        Ok(())
+        Ok(Self {
+            metadata: values_to_map(&app.metadata),
+            triggers,
+            variables: app.variables.clone(),
+            host_requirements: values_to_map(&app.host_requirements),
+            components,
+        })
     }
 
-    fn print_trigger(&self, trigger: &LockedTrigger) {
-        // TODO: printing the trigger configuration should be prettier.
-        println!(
-            "   * {} trigger: {}: {}",
-            trigger.trigger_type, trigger.id, trigger.trigger_config
-        );
-    }
+}
 
-    fn print_variables(&self, variables: &LockedMap<Variable>) {
-        if !variables.is_empty() {
+impl Render for AppInfo {
+    fn print_tables(&self) -> Result<()> {
+        let mut table = Table::new();
+        table.set_header(vec!["Key", "Value"]);
+        for (key, value) in &self.metadata {
+            table.add_row(vec![key, &value_to_string(Some(value))]);
+        }
+        println!("Appliction Info");
+        println!("{}", table);
+
+        println!("Application will be triggered by:");
+        for t in &self.triggers {
+            // TODO: printing the trigger configuration should be prettier.
+            println!(
+                "   * {} trigger: {}: {}",
+                t.trigger_type, t.id, t.trigger_config
+            );
+        }
+
+        if !self.variables.is_empty() {
             println!("Variables:");
-            for (k, v) in variables {
+            for (k, v) in &self.variables {
                 println!("   * {}: {:?}", k, v);
             }
         }
+
+        if !self.host_requirements.is_empty() {
+            println!("Host Requirements: {:?}", self.host_requirements);
+        }
+
+        for c in &self.components {
+            c.print_tables();
+        }
+
+        Ok(())
     }
+}
 
-    fn print_host_requirements(&self, requirements: &ValuesMap) {
-        if !requirements.is_empty() {
-            println!("Host Requirements: {:?}", requirements);
+impl ComponentInfo {
+    fn new(component: &LockedComponent, base_dir: Option<&Path>) -> Result<Self> {
+        let source = SourceInfo {
+            content_type: component.source.content_type.clone(),
+            // A component whose source is a build output that hasn't been built
+            // yet won't exist on disk; report that rather than erroring out.
+            size: component
+                .source
+                .content
+                .source
+                .as_deref()
+                .and_then(|uri| std::fs::metadata(resolve_source(uri, base_dir)).ok())
+                .map(|meta| meta.size()),
+        };
+
+        let mut files = Vec::new();
+        for f in &component.files {
+            let uri = f.content.source.as_deref().expect("expected content source");
+            let mut file_count = 0;
+            let mut size = 0;
+            for e in WalkDir::new(resolve_source(uri, base_dir)) {
+                let e = e?;
+                if e.file_type().is_file() {
+                    file_count += 1;
+                    size += e.metadata()?.size();
+                }
+            }
+            files.push(FileInfo {
+                path: f.path.to_string_lossy().into_owned(),
+                file_count,
+                size,
+            });
         }
+
+        Ok(Self {
+            id: component.id.clone(),
+            metadata: values_to_map(&component.metadata),
+            source,
+            env: component.env.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            files,
+        })
     }
 
-    fn print_component(&self, component: &LockedComponent) -> Result<()> {
-        println!("Component {}", component.id);
+    fn print_tables(&self) {
+        println!("Component {}", self.id);
 
         let mut table = Table::new();
         table.set_header(vec!["Field", "Value"]);
 
-        fn value_to_string(value: Option<&Value>) -> String {
-            match value {
-                Some(v) => match v {
-                    Value::String(s) => s.clone(),
-                    _ => v.to_string(),
-                },
-                None => "None".to_string(),
-            }
-        }
-
         table.add_row(vec![
-            "Description", 
-            &value_to_string(component.metadata.get("description"))
+            "Description",
+            &value_to_string(self.metadata.get("description")),
         ]);
         table.add_row(vec![
             "Allowed Outbound Hosts",
-            &value_to_string(component.metadata.get("allowed_outbound_hosts")),
+            &value_to_string(self.metadata.get("allowed_outbound_hosts")),
         ]);
         table.add_row(vec![
             "Allowed Key/Value Stores",
-            &value_to_string(component.metadata.get("key_value_stores"))
-                .replace("None", "[]"),
+            &value_to_string(self.metadata.get("key_value_stores")).replace("None", "[]"),
         ]);
         table.add_row(vec![
             "Allowed Databases",
-            &value_to_string(component.metadata.get("databases"))
-                .replace("None", "[]"),
+            &value_to_string(self.metadata.get("databases")).replace("None", "[]"),
         ]);
         table.add_row(vec![
             "Allowed AI Models",
-            &value_to_string(component.metadata.get("ai_models")),
+            &value_to_string(self.metadata.get("ai_models")),
         ]);
-    
-        if let Some(build) = component.metadata.get("build") {
-            table.add_row(vec!["Build Command", build.get("command").map_or("None", |v| v.as_str().unwrap_or_default())]);
+
+        if let Some(build) = self.metadata.get("build") {
+            table.add_row(vec![
+                "Build Command",
+                build.get("command").map_or("None", |v| v.as_str().unwrap_or_default()),
+            ]);
         } else {
             table.add_row(vec!["Build Command", "None"]);
         }
-    
-        // Print component metadata table
+
         println!("Component Information:");
         println!("{}", table);
 
-        let source = &component.source;
-        println!("   The source for component {}", component.id);
-        println!("      * content type: {}", source.content_type);
-        let size = std::fs::metadata(
-            source
-                .content
-                .source
-                .clone()
-                .expect("expected component to have wasm source")
-                .strip_prefix("file://")
-                .expect("expected source to be file URI"),
-        )?
-        .size() as f64;
-        println!("      * file size: {}", human_bytes(size));
-
-        if !&component.env.is_empty() {
+        println!("   The source for component {}", self.id);
+        println!("      * content type: {}", self.source.content_type);
+        match self.source.size {
+            Some(size) => println!("      * file size: {}", human_bytes(size as f64)),
+            None => println!("      * file size: not built"),
+        }
+
+        if !self.env.is_empty() {
             println!("   Environment variables:");
-            for (k, v) in &component.env {
+            for (k, v) in &self.env {
                 println!("      * {}={}", k, v);
             }
         }
 
-        if !&component.files.is_empty() {
+        if !self.files.is_empty() {
             println!("   Files:");
-            for f in &component.files {
-                let mut count = 0;
-                let mut size = 0;
-                let path = &f.content.source.clone().expect("expected content source");
-                for e in WalkDir::new(
-                    path.strip_prefix("file://")
-                        .expect("expected file source to be a file URI"),
-                ) {
-                    let e = e?;
-                    if e.file_type().is_file() {
-                        count += 1;
-                        size += e.metadata()?.size();
-                    }
-                }
+            for f in &self.files {
                 println!(
                     "      * {} files mounted at path {:?}, {} in total",
-                    count,
+                    f.file_count,
                     f.path,
-                    human_bytes(size as f64)
+                    human_bytes(f.size as f64)
                 );
             }
         }
+    }
+}
 
-        Ok(())
+/// Clone a [`ValuesMap`] into a plain JSON object, keeping values in their
+/// native types so strings aren't double-quoted on output.
+fn values_to_map(values: &ValuesMap) -> Map<String, Value> {
+    values.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+}
+
+/// Render a metadata value for a table cell, unquoting bare strings.
+fn value_to_string(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(v) => v.to_string(),
+        None => "None".to_string(),
     }
+}
 
-    pub async fn print_info_local(&self, _app: PathBuf) -> Result<()> {
-        todo!("Printing information about a local application not implemented yet");
+/// Resolve a `file://` content URI to a filesystem path. Local apps carry
+/// sources relative to the app directory, so join them onto `base_dir` when
+/// one is provided and the URI is relative.
+fn resolve_source(uri: &str, base_dir: Option<&Path>) -> PathBuf {
+    let path = PathBuf::from(uri.strip_prefix("file://").unwrap_or(uri));
+    match base_dir {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path,
     }
+}
 
-    fn app_source(&self) -> AppSource {
-        match &self.app_source {
-            Some(src) => AppSource::infer_source(src),
-            _ => AppSource::unresolvable("More than one application source was specified"),
+/// A summary of a registry image manifest: its config and layers, without any
+/// of the layer blobs themselves.
+#[derive(Serialize)]
+struct ManifestInfo {
+    config_media_type: String,
+    layers: Vec<LayerInfo>,
+    /// Sum of all declared layer sizes, in bytes.
+    total_size: u64,
+}
+
+#[derive(Serialize)]
+struct LayerInfo {
+    /// `wasm`, `data`, or `other`, classifying the layer by media type.
+    kind: &'static str,
+    media_type: String,
+    digest: String,
+    size: u64,
+}
+
+impl ManifestInfo {
+    fn new(manifest: &spin_oci::oci_distribution::manifest::OciImageManifest) -> Self {
+        let layers: Vec<LayerInfo> = manifest
+            .layers
+            .iter()
+            .map(|l| LayerInfo {
+                kind: match l.media_type.as_str() {
+                    WASM_LAYER_MEDIA_TYPE => "wasm",
+                    DATA_LAYER_MEDIA_TYPE => "data",
+                    _ => "other",
+                },
+                media_type: l.media_type.clone(),
+                digest: l.digest.clone(),
+                size: l.size.max(0) as u64,
+            })
+            .collect();
+
+        let total_size = layers.iter().map(|l| l.size).sum();
+
+        Self {
+            config_media_type: manifest.config.media_type.clone(),
+            layers,
+            total_size,
         }
     }
 }
+
+impl Render for ManifestInfo {
+    fn print_tables(&self) -> Result<()> {
+        let spin_config = if self.config_media_type == SPIN_CONFIG_MEDIA_TYPE {
+            " (Spin application config)"
+        } else {
+            ""
+        };
+        println!("Config media type: {}{}", self.config_media_type, spin_config);
+
+        let mut table = Table::new();
+        table.set_header(vec!["Kind", "Media Type", "Digest", "Size"]);
+        for l in &self.layers {
+            table.add_row(vec![
+                l.kind,
+                &l.media_type,
+                &l.digest,
+                &human_bytes(l.size as f64),
+            ]);
+        }
+        println!("Layers:");
+        println!("{}", table);
+
+        println!(
+            "Total compressed size: {}",
+            human_bytes(self.total_size as f64)
+        );
+
+        Ok(())
+    }
+}